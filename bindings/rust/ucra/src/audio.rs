@@ -0,0 +1,268 @@
+//! WAV file I/O for render output and reference recordings.
+//!
+//! Promoted from the copy-pasted `write_wav_float32` helper that used to
+//! live in the `emit_wav` example: samples are converted with safe
+//! little-endian arithmetic instead of `slice::from_raw_parts`, and more
+//! than one PCM format is supported.
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::RenderResult;
+
+/// PCM sample format used by `write_wav`/`read_wav`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmFormat {
+    Pcm16,
+    Pcm24,
+    Pcm32,
+    Float32,
+}
+
+impl PcmFormat {
+    fn bits_per_sample(self) -> u16 {
+        match self {
+            PcmFormat::Pcm16 => 16,
+            PcmFormat::Pcm24 => 24,
+            PcmFormat::Pcm32 => 32,
+            PcmFormat::Float32 => 32,
+        }
+    }
+
+    // WAVE_FORMAT_PCM = 1, WAVE_FORMAT_IEEE_FLOAT = 3.
+    fn format_tag(self) -> u16 {
+        match self {
+            PcmFormat::Float32 => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// Writes `result`'s PCM samples to a WAV file at `path` in `format`.
+pub fn write_wav(path: &Path, result: &RenderResult<'_>, format: PcmFormat) -> io::Result<()> {
+    let pcm = result
+        .pcm()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "render result has no pcm"))?;
+    write_wav_samples(path, pcm, result.sample_rate(), result.channels(), format)
+}
+
+fn write_wav_samples(
+    path: &Path,
+    pcm: &[f32],
+    sample_rate: u32,
+    channels: u32,
+    format: PcmFormat,
+) -> io::Result<()> {
+    let bytes_per_sample = (format.bits_per_sample() / 8) as u32;
+    let data_size = pcm.len() as u32 * bytes_per_sample;
+    let file_size = data_size + 36;
+    let byte_rate = sample_rate * channels * bytes_per_sample;
+    let block_align = (channels * bytes_per_sample) as u16;
+
+    let mut f = File::create(path)?;
+    f.write_all(b"RIFF")?;
+    f.write_all(&file_size.to_le_bytes())?;
+    f.write_all(b"WAVE")?;
+    f.write_all(b"fmt ")?;
+    f.write_all(&16u32.to_le_bytes())?;
+    f.write_all(&format.format_tag().to_le_bytes())?;
+    f.write_all(&(channels as u16).to_le_bytes())?;
+    f.write_all(&sample_rate.to_le_bytes())?;
+    f.write_all(&byte_rate.to_le_bytes())?;
+    f.write_all(&block_align.to_le_bytes())?;
+    f.write_all(&format.bits_per_sample().to_le_bytes())?;
+    f.write_all(b"data")?;
+    f.write_all(&data_size.to_le_bytes())?;
+
+    for &sample in pcm {
+        match format {
+            PcmFormat::Float32 => f.write_all(&sample.to_le_bytes())?,
+            PcmFormat::Pcm16 => f.write_all(&float_to_i16(sample).to_le_bytes())?,
+            PcmFormat::Pcm24 => f.write_all(&float_to_i24_le_bytes(sample))?,
+            PcmFormat::Pcm32 => f.write_all(&float_to_i32(sample).to_le_bytes())?,
+        }
+    }
+    Ok(())
+}
+
+fn float_to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+fn float_to_i24_le_bytes(sample: f32) -> [u8; 3] {
+    let v = (sample.clamp(-1.0, 1.0) * 8_388_607.0) as i32;
+    let b = v.to_le_bytes();
+    [b[0], b[1], b[2]]
+}
+
+fn float_to_i32(sample: f32) -> i32 {
+    (sample.clamp(-1.0, 1.0) * i32::MAX as f32) as i32
+}
+
+/// Reads a WAV file's samples back as normalized `f32`s, along with its
+/// sample rate and channel count. Supports the PCM16/PCM24/PCM32/Float32
+/// formats written by `write_wav`, so `f0_override`/`env_override` curves
+/// can be derived from reference recordings.
+pub fn read_wav(path: &Path) -> io::Result<(Vec<f32>, u32, u32)> {
+    let mut f = File::open(path)?;
+    let mut header = [0u8; 12];
+    f.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a RIFF/WAVE file"));
+    }
+
+    let mut format_tag = 0u16;
+    let mut channels = 0u32;
+    let mut sample_rate = 0u32;
+    let mut bits_per_sample = 0u16;
+    let mut pcm = Vec::new();
+    let mut saw_fmt = false;
+
+    loop {
+        let mut chunk_header = [0u8; 8];
+        if f.read_exact(&mut chunk_header).is_err() {
+            break;
+        }
+        let chunk_id = &chunk_header[0..4];
+        let chunk_size = u32::from_le_bytes(chunk_header[4..8].try_into().unwrap());
+        // RIFF chunks are padded to an even size; a trailing pad byte isn't
+        // counted in chunk_size but still has to be consumed before the next
+        // chunk header.
+        let pad = chunk_size as usize % 2;
+
+        if chunk_id == b"fmt " {
+            let mut fmt = vec![0u8; chunk_size as usize];
+            f.read_exact(&mut fmt)?;
+            format_tag = u16::from_le_bytes(fmt[0..2].try_into().unwrap());
+            channels = u16::from_le_bytes(fmt[2..4].try_into().unwrap()) as u32;
+            sample_rate = u32::from_le_bytes(fmt[4..8].try_into().unwrap());
+            bits_per_sample = u16::from_le_bytes(fmt[14..16].try_into().unwrap());
+            saw_fmt = true;
+            io::copy(&mut f.by_ref().take(pad as u64), &mut io::sink())?;
+        } else if chunk_id == b"data" {
+            let mut data = vec![0u8; chunk_size as usize];
+            f.read_exact(&mut data)?;
+            if !saw_fmt {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "data chunk before fmt chunk"));
+            }
+            pcm = decode_pcm(&data, format_tag, bits_per_sample)?;
+            io::copy(&mut f.by_ref().take(pad as u64), &mut io::sink())?;
+        } else {
+            // Skip unknown chunks (e.g. LIST, fact), padded to an even size.
+            io::copy(&mut f.by_ref().take(chunk_size as u64 + pad as u64), &mut io::sink())?;
+        }
+    }
+
+    Ok((pcm, sample_rate, channels))
+}
+
+fn decode_pcm(data: &[u8], format_tag: u16, bits_per_sample: u16) -> io::Result<Vec<f32>> {
+    match (format_tag, bits_per_sample) {
+        (3, 32) => Ok(data
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+            .collect()),
+        (1, 16) => Ok(data
+            .chunks_exact(2)
+            .map(|c| i16::from_le_bytes(c.try_into().unwrap()) as f32 / i16::MAX as f32)
+            .collect()),
+        (1, 24) => Ok(data
+            .chunks_exact(3)
+            .map(|c| {
+                let v = i32::from_le_bytes([c[0], c[1], c[2], 0]);
+                let v = (v << 8) >> 8; // sign-extend from 24 bits
+                v as f32 / 8_388_607.0
+            })
+            .collect()),
+        (1, 32) => Ok(data
+            .chunks_exact(4)
+            .map(|c| i32::from_le_bytes(c.try_into().unwrap()) as f32 / i32::MAX as f32)
+            .collect()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported wav format tag {format_tag} / {bits_per_sample}-bit"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(format: PcmFormat) {
+        let samples = [0.0f32, 0.5, -0.5, 1.0, -1.0, 0.25];
+        let path = std::env::temp_dir().join(format!("ucra_audio_roundtrip_{:?}.wav", format));
+        write_wav_samples(&path, &samples, 44100, 1, format).unwrap();
+        let (read_back, sample_rate, channels) = read_wav(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(sample_rate, 44100);
+        assert_eq!(channels, 1);
+        assert_eq!(read_back.len(), samples.len());
+        for (a, b) in samples.iter().zip(read_back.iter()) {
+            assert!((a - b).abs() < 0.01, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn roundtrip_float32() {
+        roundtrip(PcmFormat::Float32);
+    }
+
+    #[test]
+    fn roundtrip_pcm16() {
+        roundtrip(PcmFormat::Pcm16);
+    }
+
+    #[test]
+    fn roundtrip_pcm24() {
+        roundtrip(PcmFormat::Pcm24);
+    }
+
+    #[test]
+    fn roundtrip_pcm32() {
+        roundtrip(PcmFormat::Pcm32);
+    }
+
+    #[test]
+    fn read_wav_skips_pad_byte_after_odd_data_chunk() {
+        // One PCM24 sample is 3 bytes, an odd data-chunk size, so RIFF
+        // padding inserts a trailing pad byte before the next chunk. Follow
+        // it with a JUNK chunk to make sure the parser doesn't desync.
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // file size, unchecked by read_wav
+        bytes.extend_from_slice(b"WAVE");
+
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&44100u32.to_le_bytes());
+        bytes.extend_from_slice(&(44100u32 * 3).to_le_bytes()); // byte_rate
+        bytes.extend_from_slice(&3u16.to_le_bytes()); // block_align
+        bytes.extend_from_slice(&24u16.to_le_bytes()); // bits_per_sample
+
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&float_to_i24_le_bytes(0.5));
+        bytes.push(0); // RIFF pad byte for the odd-sized data chunk
+
+        bytes.extend_from_slice(b"JUNK");
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 4]);
+
+        let path = std::env::temp_dir().join("ucra_audio_odd_chunk.wav");
+        std::fs::write(&path, &bytes).unwrap();
+        let (pcm, sample_rate, channels) = read_wav(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(sample_rate, 44100);
+        assert_eq!(channels, 1);
+        assert_eq!(pcm.len(), 1);
+        assert!((pcm[0] - 0.5).abs() < 0.01);
+    }
+}
@@ -0,0 +1,132 @@
+//! Loading synthesis backends from a shared library at runtime, as an
+//! alternative to the fixed `ucra_impl` link target set up in
+//! `ucra-sys`'s `build.rs`.
+
+use std::{os::raw::c_char, path::Path, ptr, sync::Arc};
+
+use libloading::Library;
+use ucra_sys as sys;
+
+use crate::{check, manifest::ManifestHandle, Engine, Error, RenderConfig, RenderResult, Result};
+
+type CreateFn =
+    unsafe extern "C" fn(*mut sys::UCRA_Handle, *const sys::UCRA_Option, usize) -> sys::UCRA_Result;
+type DestroyFn = unsafe extern "C" fn(sys::UCRA_Handle);
+type RenderFn = unsafe extern "C" fn(
+    sys::UCRA_Handle,
+    *const sys::UCRA_RenderConfig,
+    *mut sys::UCRA_RenderResult,
+) -> sys::UCRA_Result;
+type GetInfoFn = unsafe extern "C" fn(sys::UCRA_Handle, *mut c_char, usize) -> sys::UCRA_Result;
+
+#[derive(Clone, Copy)]
+pub(crate) struct EntryPoints {
+    pub(crate) create: CreateFn,
+    pub(crate) destroy: DestroyFn,
+    pub(crate) render: RenderFn,
+    pub(crate) getinfo: GetInfoFn,
+}
+
+/// A dynamically loaded synthesis backend, resolved via `libloading` rather
+/// than linked at build time. Lets callers ship and pick between multiple
+/// UTAU-style resampler engines without recompiling the bindings.
+pub struct Backend {
+    // Kept alive for as long as any `Engine` created from this backend exists.
+    lib: Arc<Library>,
+    entry: EntryPoints,
+}
+
+impl Backend {
+    /// Opens the shared library at `path` and resolves its `ucra_*` entry
+    /// points (`ucra_engine_create`, `ucra_engine_destroy`, `ucra_render`,
+    /// `ucra_engine_getinfo`).
+    pub fn load(path: &Path) -> Result<Self> {
+        // Check absence explicitly: a Library::new failure also covers a
+        // corrupt file, wrong-architecture .so, or a permissions error, none
+        // of which is "file not found".
+        if !path.exists() {
+            return Err(Error::FileNotFound);
+        }
+        let lib = unsafe { Library::new(path) }.map_err(|_| Error::Internal)?;
+
+        let entry = unsafe {
+            let create = *lib
+                .get::<CreateFn>(b"ucra_engine_create\0")
+                .map_err(|_| Error::NotSupported)?;
+            let destroy = *lib
+                .get::<DestroyFn>(b"ucra_engine_destroy\0")
+                .map_err(|_| Error::NotSupported)?;
+            let render = *lib
+                .get::<RenderFn>(b"ucra_render\0")
+                .map_err(|_| Error::NotSupported)?;
+            let getinfo = *lib
+                .get::<GetInfoFn>(b"ucra_engine_getinfo\0")
+                .map_err(|_| Error::NotSupported)?;
+            EntryPoints { create, destroy, render, getinfo }
+        };
+
+        Ok(Self { lib: Arc::new(lib), entry })
+    }
+
+    /// Instantiates an engine bound to this backend's shared library.
+    pub fn create_engine(&self) -> Result<Engine> {
+        let mut handle: sys::UCRA_Handle = ptr::null_mut();
+        unsafe { check((self.entry.create)(&mut handle as *mut _, ptr::null(), 0))? };
+        Ok(Engine::from_dynamic(handle, self.lib.clone(), self.entry))
+    }
+}
+
+impl Engine {
+    pub(crate) fn from_dynamic(raw: sys::UCRA_Handle, lib: Arc<Library>, entry: EntryPoints) -> Self {
+        Self { raw, source: EngineSource::Dynamic { _lib: lib, entry } }
+    }
+
+    /// Wraps an engine handle created via `Manifest::create_engine`, keeping
+    /// the manifest's backing resources alive for as long as this engine
+    /// exists.
+    pub(crate) fn from_manifest(raw: sys::UCRA_Handle, manifest: Arc<ManifestHandle>) -> Self {
+        Self { raw, source: EngineSource::Manifest(manifest) }
+    }
+
+    pub(crate) fn get_info_via(&self) -> Result<String> {
+        let getinfo = match &self.source {
+            EngineSource::Linked | EngineSource::Manifest(_) => sys::ucra_engine_getinfo,
+            EngineSource::Dynamic { entry, .. } => entry.getinfo,
+        };
+        let mut buf = vec![0u8; 128];
+        let res = unsafe { getinfo(self.raw, buf.as_mut_ptr() as *mut c_char, buf.len()) };
+        check(res)?;
+        let nul = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        Ok(String::from_utf8_lossy(&buf[..nul]).to_string())
+    }
+
+    pub(crate) fn render_via(&mut self, config: &RenderConfig<'_>) -> Result<RenderResult<'_>> {
+        let render = match &self.source {
+            EngineSource::Linked | EngineSource::Manifest(_) => sys::ucra_render,
+            EngineSource::Dynamic { entry, .. } => entry.render,
+        };
+        let mut out = std::mem::MaybeUninit::<sys::UCRA_RenderResult>::zeroed();
+        unsafe { check(render(self.raw, &config.raw, out.as_mut_ptr()))? };
+        let out = unsafe { out.assume_init() };
+        Ok(RenderResult::from_raw(out))
+    }
+
+    pub(crate) fn destroy_via(&mut self) {
+        match &self.source {
+            EngineSource::Linked | EngineSource::Manifest(_) => unsafe {
+                sys::ucra_engine_destroy(self.raw)
+            },
+            EngineSource::Dynamic { entry, .. } => unsafe { (entry.destroy)(self.raw) },
+        }
+    }
+}
+
+/// Where an `Engine`'s entry points come from: statically linked via
+/// `ucra-sys`, resolved from a dynamically loaded `Backend`, or created
+/// from a `Manifest` (statically linked entry points, but the manifest's
+/// backing resources must outlive the engine).
+pub(crate) enum EngineSource {
+    Linked,
+    Dynamic { _lib: Arc<Library>, entry: EntryPoints },
+    Manifest(Arc<ManifestHandle>),
+}
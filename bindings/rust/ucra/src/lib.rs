@@ -1,8 +1,16 @@
-use std::{marker::PhantomData, mem::MaybeUninit, ptr};
+use std::{ffi::CString, marker::PhantomData, ptr};
 
 use thiserror::Error;
 use ucra_sys as sys;
 
+pub mod audio;
+pub mod backend;
+pub mod manifest;
+pub use backend::Backend;
+pub use manifest::{Capabilities, Manifest};
+
+use backend::EngineSource;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("invalid argument")]
@@ -41,37 +49,66 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 pub struct Engine {
     raw: sys::UCRA_Handle,
+    source: EngineSource,
 }
 
 impl Engine {
     pub fn new() -> Result<Self> {
         let mut handle: sys::UCRA_Handle = ptr::null_mut();
     unsafe { check(sys::ucra_engine_create(&mut handle as *mut _, ptr::null(), 0))? };
-        Ok(Self { raw: handle })
+        Ok(Self { raw: handle, source: EngineSource::Linked })
     }
 
     pub fn get_info(&self) -> Result<String> {
-        let mut buf = vec![0u8; 128];
-        let res = unsafe {
-            sys::ucra_engine_getinfo(self.raw, buf.as_mut_ptr() as *mut i8, buf.len())
-        };
-    check(res)?;
-        let nul = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
-        let s = String::from_utf8_lossy(&buf[..nul]).to_string();
-        Ok(s)
+        self.get_info_via()
     }
 
     pub fn render(&mut self, config: &RenderConfig<'_>) -> Result<RenderResult<'_>> {
-        let mut out = MaybeUninit::<sys::UCRA_RenderResult>::zeroed();
-    unsafe { check(sys::ucra_render(self.raw, &config.raw, out.as_mut_ptr()))? };
-        let out = unsafe { out.assume_init() };
-        Ok(RenderResult { raw: out, _marker: PhantomData })
+        self.render_via(config)
+    }
+
+    /// Opens a streaming render session that yields `block_size` frames at a
+    /// time instead of materializing the whole note list up front. The
+    /// returned `RenderStream` keeps its own copy of `config`'s owned note
+    /// and lyric buffers, so it stays valid independent of `config`'s
+    /// lifetime.
+    ///
+    /// Only supported for engines created via `Engine::new` or
+    /// `Manifest::create_engine`; the stream entry points aren't resolved
+    /// for dynamically loaded `Backend`s yet, so this returns
+    /// `Error::NotSupported` for those.
+    pub fn open_stream(&mut self, config: &RenderConfig<'_>) -> Result<RenderStream<'_>> {
+        if matches!(self.source, EngineSource::Dynamic { .. }) {
+            return Err(Error::NotSupported);
+        }
+        let mut raw = config.raw;
+        let mut c_notes = config.c_notes.clone();
+        let c_lyrics = config.c_lyrics.clone();
+        // c_notes was cloned by value, so each note's `lyric` pointer still
+        // points at the *original* config's CStrings. Re-point it at our own
+        // freshly cloned c_lyrics so the stream doesn't depend on `config`
+        // outliving it.
+        for (c_note, c_lyric) in c_notes.iter_mut().zip(&c_lyrics) {
+            c_note.lyric = c_lyric.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null());
+        }
+        raw.notes = c_notes.as_ptr();
+
+        let mut handle: sys::UCRA_StreamHandle = ptr::null_mut();
+        unsafe { check(sys::ucra_render_stream_open(self.raw, &raw, &mut handle as *mut _))? };
+        Ok(RenderStream {
+            raw: handle,
+            engine: self.raw,
+            config: raw,
+            c_notes,
+            c_lyrics,
+            _marker: PhantomData,
+        })
     }
 }
 
 impl Drop for Engine {
     fn drop(&mut self) {
-        unsafe { sys::ucra_engine_destroy(self.raw) }
+        self.destroy_via();
     }
 }
 
@@ -88,14 +125,14 @@ pub struct NoteSegment<'a> {
 
 impl<'a> From<&NoteSegment<'a>> for sys::UCRA_NoteSegment {
     fn from(n: &NoteSegment<'a>) -> Self {
-        // Avoid temporary CString leaks for now; pass null lyric.
-        let c_lyric = ptr::null();
+        // `lyric` is filled in by RenderConfig::new once the backing CString
+        // allocations exist; leave it null here so this impl stays infallible.
         sys::UCRA_NoteSegment {
             start_sec: n.start_sec,
             duration_sec: n.duration_sec,
             midi_note: n.midi_note,
             velocity: n.velocity,
-            lyric: c_lyric,
+            lyric: ptr::null(),
             f0_override: n.f0_override.map(|c| &c.raw as *const _).unwrap_or(ptr::null()),
             env_override: n.env_override.map(|c| &c.raw as *const _).unwrap_or(ptr::null()),
         }
@@ -109,16 +146,27 @@ pub struct EnvCurve<'a> { raw: sys::UCRA_EnvCurve, _p: PhantomData<&'a ()> }
 impl<'a> EnvCurve<'a> { pub fn new(time_sec: &'a [f32], value: &'a [f32]) -> Self { assert_eq!(time_sec.len(), value.len()); Self { raw: sys::UCRA_EnvCurve { time_sec: time_sec.as_ptr(), value: value.as_ptr(), length: time_sec.len() as u32 }, _p: PhantomData } } }
 
 pub struct RenderConfig<'a> {
-    raw: sys::UCRA_RenderConfig,
+    pub(crate) raw: sys::UCRA_RenderConfig,
     // Own the converted C notes so pointers in raw remain valid
-    c_notes: Vec<sys::UCRA_NoteSegment>,
+    pub(crate) c_notes: Vec<sys::UCRA_NoteSegment>,
+    // Own the lyric C strings so the `lyric` pointers in c_notes remain valid
+    c_lyrics: Vec<Option<CString>>,
     _p: PhantomData<&'a ()>,
 }
 
 impl<'a> RenderConfig<'a> {
-    pub fn new(sample_rate: u32, channels: u32, notes: &'a [NoteSegment<'a>]) -> Self {
+    pub fn new(sample_rate: u32, channels: u32, notes: &'a [NoteSegment<'a>]) -> Result<Self> {
         // Convert notes into owned C array to ensure lifetime safety across FFI call
-        let c_notes: Vec<sys::UCRA_NoteSegment> = notes.iter().map(|n| sys::UCRA_NoteSegment::from(n)).collect();
+        let mut c_notes: Vec<sys::UCRA_NoteSegment> = notes.iter().map(|n| sys::UCRA_NoteSegment::from(n)).collect();
+        let c_lyrics: Vec<Option<CString>> = notes
+            .iter()
+            .map(|n| n.lyric.map(CString::new).transpose().map_err(|_| Error::InvalidArgument))
+            .collect::<Result<_>>()?;
+        for (c_note, c_lyric) in c_notes.iter_mut().zip(&c_lyrics) {
+            if let Some(lyric) = c_lyric {
+                c_note.lyric = lyric.as_ptr();
+            }
+        }
         let raw = sys::UCRA_RenderConfig {
             sample_rate,
             channels,
@@ -129,7 +177,13 @@ impl<'a> RenderConfig<'a> {
             options: ptr::null(),
             option_count: 0,
         };
-        Self { raw, c_notes, _p: PhantomData }
+        Ok(Self { raw, c_notes, c_lyrics, _p: PhantomData })
+    }
+
+    /// Sets the preferred block size (in frames) that `Engine::open_stream`
+    /// asks the engine to produce per `RenderStream::pull` call.
+    pub fn set_block_size(&mut self, block_size: u32) {
+        self.raw.block_size = block_size;
     }
 }
 
@@ -139,6 +193,10 @@ pub struct RenderResult<'a> {
 }
 
 impl<'a> RenderResult<'a> {
+    pub(crate) fn from_raw(raw: sys::UCRA_RenderResult) -> Self {
+        Self { raw, _marker: PhantomData }
+    }
+
     pub fn pcm(&self) -> Option<&[f32]> {
         if self.raw.pcm.is_null() || self.raw.frames == 0 { return None; }
         let len = (self.raw.frames as usize) * (self.raw.channels as usize);
@@ -149,6 +207,46 @@ impl<'a> RenderResult<'a> {
     pub fn sample_rate(&self) -> u32 { self.raw.sample_rate }
 }
 
+/// A block-at-a-time render session opened via `Engine::open_stream`.
+///
+/// Each `pull` asks the engine for the next chunk of frames instead of
+/// rendering the whole note list into memory, so callers can feed an audio
+/// callback without a multi-minute `RenderResult` buffer.
+pub struct RenderStream<'a> {
+    raw: sys::UCRA_StreamHandle,
+    engine: sys::UCRA_Handle,
+    // Owned copy of the config so the stream outlives the `RenderConfig` used to open it.
+    config: sys::UCRA_RenderConfig,
+    c_notes: Vec<sys::UCRA_NoteSegment>,
+    c_lyrics: Vec<Option<CString>>,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> RenderStream<'a> {
+    /// Fills `out` with interleaved samples and returns the number of
+    /// frames written. Returns `0` once the stream is exhausted.
+    pub fn pull(&mut self, out: &mut [f32]) -> Result<usize> {
+        let channels = self.config.channels.max(1) as usize;
+        let frame_capacity = out.len() / channels;
+        let mut frames_written: u64 = 0;
+        unsafe {
+            check(sys::ucra_render_stream_read(
+                self.raw,
+                out.as_mut_ptr(),
+                frame_capacity as u32,
+                &mut frames_written as *mut _,
+            ))?;
+        }
+        Ok(frames_written as usize)
+    }
+}
+
+impl<'a> Drop for RenderStream<'a> {
+    fn drop(&mut self) {
+        unsafe { sys::ucra_render_stream_close(self.engine, self.raw) }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,10 +263,114 @@ mod tests {
         let mut eng = Engine::new().unwrap();
     let note = NoteSegment { start_sec: 0.0, duration_sec: 0.1, midi_note: 69, velocity: 100, lyric: None, f0_override: None, env_override: None };
     let notes = [note];
-    let cfg = RenderConfig::new(44100, 1, &notes);
+    let cfg = RenderConfig::new(44100, 1, &notes).unwrap();
         let out = eng.render(&cfg).unwrap();
         assert!(out.frames() > 0);
         let pcm = out.pcm().unwrap();
         assert!(!pcm.is_empty());
     }
+
+    #[test]
+    fn render_with_lyric() {
+        let mut eng = Engine::new().unwrap();
+        let note = NoteSegment { start_sec: 0.0, duration_sec: 0.1, midi_note: 69, velocity: 100, lyric: Some("la"), f0_override: None, env_override: None };
+        let notes = [note];
+        let cfg = RenderConfig::new(44100, 1, &notes).unwrap();
+        let out = eng.render(&cfg).unwrap();
+        assert!(out.frames() > 0);
+    }
+
+    #[test]
+    fn stream_pulls_blocks() {
+        let mut eng = Engine::new().unwrap();
+        let note = NoteSegment { start_sec: 0.0, duration_sec: 0.2, midi_note: 69, velocity: 100, lyric: None, f0_override: None, env_override: None };
+        let notes = [note];
+        let mut cfg = RenderConfig::new(44100, 1, &notes).unwrap();
+        cfg.set_block_size(256);
+        let mut stream = eng.open_stream(&cfg).unwrap();
+
+        let mut buf = vec![0.0f32; 256];
+        let mut total_frames = 0usize;
+        loop {
+            let n = stream.pull(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            total_frames += n;
+        }
+        assert!(total_frames > 0);
+    }
+
+    #[test]
+    fn stream_outlives_source_config() {
+        let mut eng = Engine::new().unwrap();
+        let note = NoteSegment { start_sec: 0.0, duration_sec: 0.2, midi_note: 69, velocity: 100, lyric: Some("la"), f0_override: None, env_override: None };
+        let notes = [note];
+        let mut cfg = RenderConfig::new(44100, 1, &notes).unwrap();
+        cfg.set_block_size(256);
+        let mut stream = eng.open_stream(&cfg).unwrap();
+        drop(cfg);
+
+        let mut buf = vec![0.0f32; 256];
+        let mut total_frames = 0usize;
+        loop {
+            let n = stream.pull(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            total_frames += n;
+        }
+        assert!(total_frames > 0);
+    }
+
+    #[test]
+    fn backend_rejects_missing_library() {
+        let err = Backend::load(std::path::Path::new("/nonexistent/libucra_impl.so")).unwrap_err();
+        assert!(matches!(err, Error::FileNotFound));
+    }
+
+    #[test]
+    fn backend_reports_internal_error_for_unloadable_file() {
+        // The file exists but isn't a valid shared library, so this must be
+        // distinguished from FileNotFound.
+        let path = std::env::temp_dir().join("ucra_backend_not_a_library.so");
+        std::fs::write(&path, b"not a shared library").unwrap();
+        let err = Backend::load(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(err, Error::Internal));
+    }
+
+    #[test]
+    fn dynamic_engine_rejects_open_stream() {
+        // Stream entry points aren't resolved for dynamically loaded
+        // backends yet, so open_stream must fail cleanly instead of handing
+        // a statically-linked stream function a handle from another library.
+        unsafe extern "C" fn noop_create(_: *mut sys::UCRA_Handle, _: *const sys::UCRA_Option, _: usize) -> sys::UCRA_Result { 0 }
+        unsafe extern "C" fn noop_destroy(_: sys::UCRA_Handle) {}
+        unsafe extern "C" fn noop_render(_: sys::UCRA_Handle, _: *const sys::UCRA_RenderConfig, _: *mut sys::UCRA_RenderResult) -> sys::UCRA_Result { 0 }
+        unsafe extern "C" fn noop_getinfo(_: sys::UCRA_Handle, _: *mut std::os::raw::c_char, _: usize) -> sys::UCRA_Result { 0 }
+
+        // A dlopen handle to ourselves is enough to satisfy the `Arc<Library>`
+        // the engine keeps alive; we don't resolve any symbols through it.
+        let lib = unsafe { libloading::Library::new(std::env::current_exe().unwrap()) }.unwrap();
+        let entry = backend::EntryPoints {
+            create: noop_create,
+            destroy: noop_destroy,
+            render: noop_render,
+            getinfo: noop_getinfo,
+        };
+        let mut eng = Engine::from_dynamic(ptr::null_mut(), std::sync::Arc::new(lib), entry);
+
+        let note = NoteSegment { start_sec: 0.0, duration_sec: 0.1, midi_note: 69, velocity: 100, lyric: None, f0_override: None, env_override: None };
+        let notes = [note];
+        let cfg = RenderConfig::new(44100, 1, &notes).unwrap();
+        assert!(matches!(eng.open_stream(&cfg), Err(Error::NotSupported)));
+    }
+
+    #[test]
+    fn lyric_with_interior_nul_is_rejected() {
+        let note = NoteSegment { start_sec: 0.0, duration_sec: 0.1, midi_note: 69, velocity: 100, lyric: Some("la\0la"), f0_override: None, env_override: None };
+        let notes = [note];
+        assert!(matches!(RenderConfig::new(44100, 1, &notes), Err(Error::InvalidArgument)));
+    }
 }
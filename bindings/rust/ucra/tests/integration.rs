@@ -8,7 +8,7 @@ fn basic_render_flow() {
 
     let note = NoteSegment { start_sec: 0.0, duration_sec: 0.05, midi_note: 60, velocity: 127, lyric: None, f0_override: None, env_override: None };
     let notes = [note];
-    let cfg = RenderConfig::new(44100, 1, &notes);
+    let cfg = RenderConfig::new(44100, 1, &notes).unwrap();
     let out = eng.render(&cfg).unwrap();
     assert!(out.frames() > 0);
     let pcm = out.pcm().unwrap();